@@ -6,7 +6,7 @@ use {
     solana_sdk::{
         commitment_config::CommitmentConfig,
         pubkey::Pubkey,
-        signature::{read_keypair, Keypair},
+        signature::{read_keypair, read_keypair_file, Keypair},
     },
     std::{
         convert::identity,
@@ -16,15 +16,17 @@ use {
             atomic::{AtomicUsize, Ordering},
             Arc,
         },
+        time::Duration,
     },
     tokio::{
         runtime::Builder,
         signal::unix::{signal, SignalKind},
-        sync::{broadcast, oneshot},
+        sync::{broadcast, oneshot, watch},
         task::JoinHandle,
     },
     tracing::{info, warn},
     solana_highway::{
+        bench::{run_bench, BenchParams},
         blockhash_queue::BlockhashQueue,
         cluster_tpu_info::ClusterTpuInfo,
         config::{load_config, ConfigHighway, ConfigHighwayGatewayClient, ConfigMetricsUpstream},
@@ -33,12 +35,13 @@ use {
         grpc_highway::GrpcServer,
         grpc_metrics::GrpcClient as GrpcMetricsClient,
         metrics::highway as metrics,
+        postgres::PostgresLogger,
         quic::{QuicClient, QuicClientMetric},
         quic_solana::ConnectionCache,
         rpc::{rpc_admin::RpcClient, rpc_solana_like::RpcServerImpl, RpcServer, RpcServerType},
         setup_tracing,
         stake::StakeInfo,
-        task_group::TaskGroup,
+        task_group::{RestartPolicy, TaskGroup},
         transactions::{GrpcRootedTxReceiver, SendTransactionsPool},
         util::{IdentityFlusherWaitGroup, PubkeySigner, ValueObserver, WaitShutdown},
     },
@@ -55,6 +58,13 @@ struct Args {
     #[clap(long, default_value_t = false)]
     pub check: bool,
 
+    /// Path to a keypair file used as the startup identity. Consulted only
+    /// after an identity configured inline in the config file and the
+    /// `HIGHWAY_IDENTITY` environment variable, and before falling back to an
+    /// ephemeral key.
+    #[clap(long)]
+    pub identity_keypair: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Option<ArgsCommands>,
 }
@@ -66,6 +76,31 @@ enum ArgsCommands {
         #[command(subcommand)]
         cmd: ArgsCommandAdmin,
     },
+    /// Drive synthetic transaction load through the send pipeline
+    Bench(ArgsCommandBench),
+}
+
+#[derive(Debug, Parser)]
+struct ArgsCommandBench {
+    /// Path to the funding keypair that signs and pays for the load
+    #[clap(long)]
+    pub funding_keypair: PathBuf,
+
+    /// Target pubkey used as the memo/no-op transaction recipient
+    #[clap(long)]
+    pub target: Pubkey,
+
+    /// Target transactions per second
+    #[clap(long, default_value_t = 100)]
+    pub tps: u64,
+
+    /// How long to run the benchmark, in seconds
+    #[clap(long, default_value_t = 30)]
+    pub duration: u64,
+
+    /// Number of in-flight transactions to keep outstanding
+    #[clap(long, default_value_t = 256)]
+    pub concurrency: usize,
 }
 
 #[derive(Debug, Subcommand)]
@@ -105,8 +140,49 @@ async fn main2() -> anyhow::Result<()> {
 
     match args.command {
         Some(ArgsCommands::Admin { cmd }) => run_cmd_admin(config, cmd).await,
-        None => run_highway(config).await,
+        Some(ArgsCommands::Bench(bench)) => run_cmd_bench(config, bench).await,
+        None => run_highway(config, args.identity_keypair).await,
+    }
+}
+
+/// Resolve the keypair the service starts with, following the documented order:
+/// (1) an identity configured inline/by path in the config file, (2) the
+/// `HIGHWAY_IDENTITY` environment variable pointing at a keypair file, (3) the
+/// `--identity-keypair` CLI argument, and only then (4) an ephemeral key.
+///
+/// Falling through to the ephemeral key is logged, and is a hard error when
+/// `identity.expected` is set: a deployment that pins an expected identity must
+/// never silently run with a throwaway key that can never match it.
+fn resolve_initial_identity(
+    config_keypair: Option<Keypair>,
+    cli_keypair: Option<PathBuf>,
+    expected: Option<Pubkey>,
+) -> anyhow::Result<Keypair> {
+    if let Some(keypair) = config_keypair {
+        return Ok(keypair);
+    }
+
+    if let Ok(path) = std::env::var("HIGHWAY_IDENTITY") {
+        let keypair = read_keypair_file(&path)
+            .map_err(|error| anyhow::anyhow!(error.to_string()))
+            .with_context(|| format!("failed to read HIGHWAY_IDENTITY keypair: {path}"))?;
+        return Ok(keypair);
+    }
+
+    if let Some(path) = cli_keypair {
+        let keypair = read_keypair_file(&path)
+            .map_err(|error| anyhow::anyhow!(error.to_string()))
+            .with_context(|| format!("failed to read --identity-keypair: {path:?}"))?;
+        return Ok(keypair);
     }
+
+    anyhow::ensure!(
+        expected.is_none(),
+        "identity.expected is set but no keypair was resolved from config, \
+         HIGHWAY_IDENTITY, or --identity-keypair; refusing to start with an ephemeral identity"
+    );
+    warn!("no identity configured, starting with an ephemeral keypair");
+    Ok(Keypair::new())
 }
 
 async fn run_cmd_admin(config: ConfigHighway, admin_cmd: ArgsCommandAdmin) -> anyhow::Result<()> {
@@ -152,6 +228,28 @@ async fn run_cmd_admin(config: ConfigHighway, admin_cmd: ArgsCommandAdmin) -> an
     Ok(())
 }
 
+async fn run_cmd_bench(config: ConfigHighway, bench: ArgsCommandBench) -> anyhow::Result<()> {
+    let funding_keypair = read_keypair_file(&bench.funding_keypair)
+        .map_err(|error| anyhow::anyhow!(error.to_string()))
+        .with_context(|| format!("failed to read funding keypair: {:?}", bench.funding_keypair))?;
+
+    // Exercise the real routing logic: the bench harness stands up the same
+    // BlockhashQueue, ClusterTpuInfo, QuicClient and SendTransactionsPool the
+    // service uses, submits the synthetic load through them, and correlates
+    // landed signatures against the rooted-transaction stream.
+    run_bench(
+        config,
+        BenchParams {
+            funding_keypair,
+            target: bench.target,
+            tps: bench.tps,
+            duration: Duration::from_secs(bench.duration),
+            concurrency: bench.concurrency,
+        },
+    )
+    .await
+}
+
 async fn spawn_highway_gw_listener(
     highway_gw_config: ConfigHighwayGatewayClient,
     mut identity_observer: ValueObserver<PubkeySigner>,
@@ -239,6 +337,15 @@ fn spawn_lewis_metric_subscriber(
                             leader_tpu_addr,
                             error,
                         );
+                        metrics::quic_observe_send_attempt(&leader, &leader_tpu_addr);
+                    }
+                    QuicClientMetric::ConnectionError {
+                        leader,
+                        leader_tpu_addr,
+                        kind,
+                    } => {
+                        grpc_metrics.emit_connection_error(&leader, leader_tpu_addr, kind);
+                        metrics::quic_inc_connection_error(&leader, &leader_tpu_addr, kind);
                     }
                 },
                 Err(broadcast::error::RecvError::Closed) => {
@@ -252,21 +359,27 @@ fn spawn_lewis_metric_subscriber(
     })
 }
 
-async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
+async fn run_highway(
+    config: ConfigHighway,
+    identity_keypair: Option<PathBuf>,
+) -> anyhow::Result<()> {
     metrics::init();
     if let Some(identity) = config.identity.expected {
         metrics::quic_set_identity_expected(identity);
     }
     // let flush_identity = Arc::new(flush_identity);
     let (shutdown_geyser_tx, shutdown_geyser_rx) = oneshot::channel();
-    let (geyser, mut geyser_handle) = GeyserSubscriber::new(
-        shutdown_geyser_rx,
-        config.upstream.primary_grpc.clone(),
-        config
-            .upstream
-            .secondary_grpc
-            .unwrap_or(config.upstream.primary_grpc),
-    );
+    // Collect every configured gRPC source into a single list: the primary is
+    // always present, the (legacy) secondary is appended when set, and any
+    // number of extra sources may be listed. `GeyserSubscriber` subscribes to
+    // all of them concurrently and dedups notifications per slot, so redundant
+    // endpoints transparently cover for a dead or lagging one.
+    let mut grpc_sources = vec![config.upstream.primary_grpc.clone()];
+    if let Some(secondary_grpc) = config.upstream.secondary_grpc.clone() {
+        grpc_sources.push(secondary_grpc);
+    }
+    grpc_sources.extend(config.upstream.extra_grpc.iter().cloned());
+    let (geyser, mut geyser_handle) = GeyserSubscriber::new(shutdown_geyser_rx, grpc_sources);
     let blockhash_queue = BlockhashQueue::new(&geyser);
     let cluster_tpu_info = ClusterTpuInfo::new(
         config.upstream.rpc.clone(),
@@ -285,7 +398,11 @@ async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
 
     let identity_flusher_wg = IdentityFlusherWaitGroup::default();
 
-    let initial_identity = config.identity.keypair.unwrap_or(Keypair::new());
+    let initial_identity = resolve_initial_identity(
+        config.identity.keypair,
+        identity_keypair,
+        config.identity.expected,
+    )?;
     let (quic_session, quic_identity_man) = ConnectionCache::new(
         config.quic.clone(),
         initial_identity,
@@ -314,15 +431,29 @@ async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
         .add_flusher(Box::new(send_transactions.clone()))
         .await;
 
-    let rpc = solana_client::nonblocking::rpc_client::RpcClient::new_with_commitment(
-        config.upstream.rpc.clone(),
-        CommitmentConfig::finalized(),
-    );
-    let stake = StakeInfo::new(
-        rpc,
-        config.upstream.stake_update_interval,
-        quic_identity_man.observe_identity_change(),
-    );
+    // Optional durable audit trail: record the full lifecycle of each
+    // transaction (received signature/slot, every QUIC send attempt, the
+    // blockhash/last-valid-block-height it was signed against, and its final
+    // rooted/dropped status) to Postgres. Inserts are batched over a bounded
+    // channel so DB latency never backpressures the send path.
+    let postgres_fut = if let Some(config_postgres) = config.postgres.clone() {
+        Some(
+            PostgresLogger::spawn(
+                config_postgres,
+                quic_tx_sender.subscribe_metrics(),
+                Arc::new(blockhash_queue.clone()),
+                send_transactions.clone(),
+            )
+            .await?,
+        )
+    } else {
+        warn!("Skipping Postgres logger, no postgres config provided");
+        None
+    };
+
+    // Observer the supervised `stake` task re-subscribes to on every (re)spawn;
+    // captured before `quic_identity_man` is moved into the admin RPC server.
+    let stake_identity_observer = quic_identity_man.observe_identity_change();
 
     let quic_identity_observer = quic_identity_man.observe_signer_change();
     // Run RPC admin
@@ -347,6 +478,16 @@ async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
     )
     .await?;
 
+    // Local Prometheus exposition endpoint, so operators can scrape the
+    // counters/histograms `metrics::highway` registers without depending on the
+    // gRPC metrics collector.
+    let prometheus = if let Some(listen_metrics) = config.listen_metrics.clone() {
+        Some(metrics::spawn_prometheus_exporter(listen_metrics.bind[0]).await?)
+    } else {
+        warn!("Skipping Prometheus exporter, no listen_metrics config provided");
+        None
+    };
+
     // Run gRPC to highway-gateway
     let (stop_highway_gw_listener_tx, stop_highway_gw_listener_rx) = oneshot::channel();
     let highway_gw_listener = if let Some(config_highway_gateway) = config.highway_gateway {
@@ -390,6 +531,12 @@ async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
 
     let mut sigint = signal(SignalKind::interrupt())?;
 
+    // Coordinated graceful-shutdown signal for supervised tasks. A latching
+    // `watch` channel (rather than `Notify`, whose wakeups don't persist) so a
+    // task caught mid-backoff or mid-rebuild still observes the request on its
+    // next (re)spawn and tears itself down instead of respawning.
+    let (supervised_shutdown_tx, supervised_shutdown_rx) = watch::channel(false);
+
     let mut tg = TaskGroup::default();
 
     tg.spawn_cancelable("lewis", async move {
@@ -420,17 +567,40 @@ async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
         }
     });
 
-    tg.spawn_with_shutdown("cluster_tpu_info", |mut stop| async move {
-        tokio::select! {
-            result = cluster_tpu_info.clone().wait_shutdown() => {
-                result.expect("cluster_tpu_info");
-            },
-            _ = &mut stop => {
-                cluster_tpu_info.shutdown();
-                cluster_tpu_info.wait_shutdown().await.expect("cluster_tpu_info shutdown");
-            },
-        }
-    });
+    // Supervised: an error from cluster_tpu_info is returned to the supervisor
+    // instead of panicking, so it does not take down the whole service. The
+    // factory re-awaits the *same* instance `QuicClient` holds (via a shared
+    // handle); it does not build a new `ClusterTpuInfo`, so a refresher that
+    // has exited is not started again.
+    tg.spawn_supervised(
+        "cluster_tpu_info",
+        {
+            let cluster_tpu_info = cluster_tpu_info.clone();
+            let shutdown = supervised_shutdown_rx.clone();
+            move || {
+                let cluster_tpu_info = cluster_tpu_info.clone();
+                let mut shutdown = shutdown.clone();
+                async move {
+                    if *shutdown.borrow_and_update() {
+                        return Ok(());
+                    }
+                    tokio::select! {
+                        result = cluster_tpu_info.clone().wait_shutdown() => {
+                            result.map_err(|error| anyhow::anyhow!("{error:?}"))
+                        },
+                        _ = shutdown.changed() => {
+                            cluster_tpu_info.shutdown();
+                            cluster_tpu_info
+                                .wait_shutdown()
+                                .await
+                                .map_err(|error| anyhow::anyhow!("{error:?}"))
+                        },
+                    }
+                }
+            }
+        },
+        RestartPolicy::default(),
+    );
 
     tg.spawn_cancelable("rooted_transactions", async move {
         rooted_tx_loop_fut.await;
@@ -438,17 +608,51 @@ async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
 
     tg.spawn_cancelable("send_transactions_pool", send_tx_pool_fut);
 
-    tg.spawn_with_shutdown("stake", |mut stop| async move {
-        tokio::select! {
-            result = stake.clone().wait_shutdown() => {
-                result.expect("stake");
-            },
-            _ = &mut stop => {
-                stake.shutdown();
-                stake.wait_shutdown().await.expect("stake shutdown");
-            },
-        }
-    });
+    if let Some(postgres_fut) = postgres_fut {
+        tg.spawn_cancelable("postgres", postgres_fut);
+    }
+
+    // Supervised: stake refreshes periodically from upstream RPC; a transient
+    // error should rebuild the refresher, not kill the service. The factory
+    // reconnects the RPC client and re-subscribes to identity changes on every
+    // (re)spawn, and returns the error to the supervisor instead of panicking.
+    tg.spawn_supervised(
+        "stake",
+        {
+            let rpc_url = config.upstream.rpc.clone();
+            let update_interval = config.upstream.stake_update_interval;
+            let identity_observer = stake_identity_observer;
+            let shutdown = supervised_shutdown_rx.clone();
+            move || {
+                let rpc_url = rpc_url.clone();
+                let identity_observer = identity_observer.clone();
+                let mut shutdown = shutdown.clone();
+                async move {
+                    if *shutdown.borrow_and_update() {
+                        return Ok(());
+                    }
+                    let rpc = solana_client::nonblocking::rpc_client::RpcClient::new_with_commitment(
+                        rpc_url,
+                        CommitmentConfig::finalized(),
+                    );
+                    let stake = StakeInfo::new(rpc, update_interval, identity_observer);
+                    tokio::select! {
+                        result = stake.clone().wait_shutdown() => {
+                            result.map_err(|error| anyhow::anyhow!("{error:?}"))
+                        },
+                        _ = shutdown.changed() => {
+                            stake.shutdown();
+                            stake
+                                .wait_shutdown()
+                                .await
+                                .map_err(|error| anyhow::anyhow!("{error:?}"))
+                        },
+                    }
+                }
+            }
+        },
+        RestartPolicy::default(),
+    );
 
     if let Some(mut highway_gw_listener) = highway_gw_listener {
         tg.spawn_with_shutdown("highway_gw_listener", |mut stop| async move {
@@ -470,8 +674,12 @@ async fn run_highway(config: ConfigHighway) -> anyhow::Result<()> {
     });
 
     let (first, result, rest) = tg.wait_one().await.expect("task group empty");
+    let _ = supervised_shutdown_tx.send(true);
     rpc_admin.shutdown();
     rpc_solana_like.shutdown();
+    if let Some(prometheus) = prometheus {
+        prometheus.shutdown();
+    }
 
     warn!("first task group finished {first} with  {result:?}");
 